@@ -0,0 +1,58 @@
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use crate::{analyze_document_key, AppState};
+use crate::error::AppError;
+
+// How many documents to have in flight against Textract at once, to stay under
+// Textract's per-account transactions-per-second limit.
+const MAX_CONCURRENT_DOCUMENTS: usize = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    pub keys: Vec<String>,
+}
+
+// Analyze a list of S3 keys concurrently (bounded by MAX_CONCURRENT_DOCUMENTS) and
+// return the per-document key-value pairs, keyed by filename, as one zstd-compressed
+// JSON body. A failure analyzing one document is reported under its own key instead of
+// failing the whole batch.
+pub async fn analyze_batch(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BatchRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let results: Vec<(String, Result<Vec<_>, String>)> = stream::iter(request.keys)
+        .map(|key| {
+            let state = state.clone();
+            async move {
+                let result = analyze_document_key(&state, &key)
+                    .await
+                    .map_err(|e| e.to_string());
+                (key, result)
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_DOCUMENTS)
+        .collect()
+        .await;
+
+    let by_key: HashMap<String, Result<Vec<_>, String>> = results.into_iter().collect();
+
+    let json_body = serde_json::to_vec(&by_key)
+        .map_err(|e| AppError::Internal(format!("failed to serialize batch result: {e}")))?;
+    let compressed = zstd::stream::encode_all(Cursor::new(json_body), 0)
+        .map_err(|e| AppError::Internal(format!("failed to compress batch result: {e}")))?;
+
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, "application/json"),
+            (axum::http::header::CONTENT_ENCODING, "zstd"),
+        ],
+        compressed,
+    ))
+}
@@ -0,0 +1,47 @@
+use std::env;
+
+// Runtime configuration for the service, loaded from environment variables so the same
+// binary can target real AWS or any S3-compatible store (MinIO, Garage, Ceph).
+#[derive(Clone)]
+pub struct Config {
+    pub bucket: String,
+    pub region: String,
+    // Optional custom S3 endpoint, e.g. http://localhost:9000 for MinIO.
+    pub s3_endpoint_url: Option<String>,
+    // Address the bucket as endpoint/bucket/key instead of bucket.endpoint/key. Required
+    // by most self-hosted S3-compatible stores.
+    pub s3_force_path_style: bool,
+    // Explicit static credentials, used instead of the default provider chain when set.
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+}
+
+impl std::fmt::Debug for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Config")
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("s3_endpoint_url", &self.s3_endpoint_url)
+            .field("s3_force_path_style", &self.s3_force_path_style)
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &self.secret_access_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl Config {
+    // Load configuration from the environment, falling back to the defaults this
+    // service has always used against real AWS.
+    pub fn from_env() -> Self {
+        Self {
+            bucket: env::var("TEX_S3_BUCKET").unwrap_or_else(|_| "smartflow-dev".to_string()),
+            region: env::var("TEX_AWS_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            s3_endpoint_url: env::var("TEX_S3_ENDPOINT_URL").ok(),
+            s3_force_path_style: env::var("TEX_S3_FORCE_PATH_STYLE")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            access_key_id: env::var("TEX_S3_ACCESS_KEY_ID").ok(),
+            secret_access_key: env::var("TEX_S3_SECRET_ACCESS_KEY").ok(),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use aws_smithy_runtime_api::client::result::SdkError;
+use aws_smithy_types::error::metadata::ProvideErrorMetadata;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+// Error codes that indicate a throttled/transient failure and are safe to retry.
+const RETRYABLE_CODES: &[&str] = &[
+    "ThrottlingException",
+    "ProvisionedThroughputExceededException",
+    "RequestLimitExceeded",
+    "InternalServerError",
+    "ServiceUnavailable",
+    "SlowDown",
+];
+
+pub(crate) fn is_retryable_code(code: &str) -> bool {
+    RETRYABLE_CODES.contains(&code)
+}
+
+// Error returned to callers once a request has exhausted its retries (or failed for a
+// non-retryable reason), mapped to the appropriate HTTP status.
+#[derive(Debug)]
+pub enum AppError {
+    // Still being throttled after retrying; tell the caller to back off.
+    Throttled(String),
+    // Anything else: a non-retryable upstream error or a local failure.
+    Internal(String),
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::Throttled(message) | AppError::Internal(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        match self {
+            AppError::Throttled(message) => {
+                (StatusCode::TOO_MANY_REQUESTS, message).into_response()
+            }
+            AppError::Internal(message) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, message).into_response()
+            }
+        }
+    }
+}
+
+impl<E, R> From<SdkError<E, R>> for AppError
+where
+    E: ProvideErrorMetadata,
+{
+    fn from(err: SdkError<E, R>) -> Self {
+        let code = err.code().unwrap_or("Unknown").to_string();
+        let message = err
+            .message()
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| code.clone());
+
+        if is_retryable_code(&code) {
+            AppError::Throttled(format!("request throttled after retries ({code}): {message}"))
+        } else {
+            AppError::Internal(format!("upstream error ({code}): {message}"))
+        }
+    }
+}
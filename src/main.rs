@@ -1,65 +1,126 @@
 use aws_config::meta::region::RegionProviderChain;
 use aws_config::Region;
+use aws_credential_types::provider::SharedCredentialsProvider;
+use aws_credential_types::Credentials;
+use aws_sdk_s3::config::Builder as S3ConfigBuilder;
 use aws_sdk_s3::Client as S3Client;
-use aws_sdk_textract::types::{Block, BlockType, Document, EntityType, RelationshipType};
+use aws_sdk_textract::types::{
+    Block, BlockType, Document, DocumentLocation, EntityType, JobStatus, RelationshipType,
+    S3Object,
+};
 use aws_sdk_textract::Client as TextractClient;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
+use axum::http::header::ACCEPT;
+use axum::http::HeaderMap;
 use axum::response::IntoResponse;
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{Json, Router};
 use image::{ImageBuffer, Rgb};
 use imageproc::drawing::{draw_hollow_rect_mut, draw_text_mut};
 use imageproc::rect::Rect;
 use rusttype::{Font, Scale};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::io::Cursor;
 use std::sync::Arc;
+use std::time::Duration;
+
+mod batch;
+mod config;
+mod error;
+mod output_format;
+mod queries;
+mod retry;
+mod tables;
+
+use config::Config;
+use error::AppError;
+use output_format::{encode_image, OutputFormat};
+use retry::{backoff_delay_with, retry};
+
+// Base delay between `get_document_analysis` polls, before backoff growth.
+const POLL_BASE_DELAY: Duration = Duration::from_secs(2);
+// Cap on the backed-off poll delay.
+const POLL_MAX_DELAY: Duration = Duration::from_secs(30);
+// Give up on a job that hasn't finished after this many polls.
+const MAX_POLL_ATTEMPTS: u32 = 150;
 
 #[derive(Debug, Serialize)]
-struct KeyValuePair {
+pub(crate) struct KeyValuePair {
     key: String,
     value: String,
     key_bounding_box: Option<BoundingBox>,
     value_bounding_box: Option<BoundingBox>,
+    page: i32,
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct BoundingBox {
+pub(crate) struct BoundingBox {
     width: f32,
     height: f32,
     left: f32,
     top: f32,
 }
 
-struct AppState {
+pub(crate) struct AppState {
     textract_client: TextractClient,
     s3_client: S3Client,
+    config: Config,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let app_config = Config::from_env();
+
     // Set up the AWS region
-    let region_provider = RegionProviderChain::default_provider().or_else(Region::new("us-east-1"));
+    let region_provider = RegionProviderChain::default_provider()
+        .or_else(Region::new(app_config.region.clone()));
 
     // Load configuration
     #[allow(deprecated)]
-    let config = aws_config::from_env().region(region_provider).load().await;
+    let sdk_config = aws_config::from_env().region(region_provider).load().await;
 
     // Create a Textract client
-    let textract_client = TextractClient::new(&config);
-    let s3_client = S3Client::new(&config);
+    let textract_client = TextractClient::new(&sdk_config);
+
+    // Build the S3 client, optionally pointing it at a self-hosted S3-compatible
+    // store (MinIO, Garage, Ceph) instead of real AWS.
+    let mut s3_config_builder = S3ConfigBuilder::from(&sdk_config);
+    if let Some(endpoint_url) = &app_config.s3_endpoint_url {
+        s3_config_builder = s3_config_builder.endpoint_url(endpoint_url);
+    }
+    if app_config.s3_force_path_style {
+        s3_config_builder = s3_config_builder.force_path_style(true);
+    }
+    if let (Some(access_key_id), Some(secret_access_key)) =
+        (&app_config.access_key_id, &app_config.secret_access_key)
+    {
+        let credentials = Credentials::new(
+            access_key_id,
+            secret_access_key,
+            None,
+            None,
+            "tex-config",
+        );
+        s3_config_builder =
+            s3_config_builder.credentials_provider(SharedCredentialsProvider::new(credentials));
+    }
+    let s3_client = S3Client::from_conf(s3_config_builder.build());
 
     // Create app state
     let app_state = Arc::new(AppState {
         textract_client,
         s3_client,
+        config: app_config,
     });
 
     // Build our application with a route
     let app = Router::new()
         .route("/analyze/:image_name", get(analyze_image))
         .route("/display/:image_name", get(display_image))
+        .route("/analyze-async/:doc_name", get(analyze_document_async))
+        .route("/analyze/batch", post(batch::analyze_batch))
+        .route("/tables/:image_name", get(tables::tables_for_image))
+        .route("/query/:image_name", post(queries::query_image))
         .with_state(app_state);
 
     // Run our application
@@ -71,46 +132,76 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+// Query params accepted by `/display/:image_name` to pick the output encoding.
+#[derive(Debug, Deserialize)]
+struct DisplayParams {
+    format: Option<String>,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+}
+
+const DEFAULT_OUTPUT_QUALITY: u8 = 85;
+
 async fn display_image(
     Path(image_name): Path<String>,
+    Query(params): Query<DisplayParams>,
+    headers: HeaderMap,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    let bucket = "smartflow-dev";
+) -> Result<impl IntoResponse, AppError> {
+    let output_format = OutputFormat::negotiate(
+        params.format.as_deref(),
+        headers.get(ACCEPT).and_then(|v| v.to_str().ok()),
+    );
+    let quality = params.quality.unwrap_or(DEFAULT_OUTPUT_QUALITY);
+    let lossless = params.lossless.unwrap_or(false);
+
+    let bucket = state.config.bucket.as_str();
 
     // Fetch image from S3
-    let get_object_output = state
-        .s3_client
-        .get_object()
-        .bucket(bucket)
-        .key(&image_name)
-        .send()
+    let get_object_output = retry(|| {
+        state
+            .s3_client
+            .get_object()
+            .bucket(bucket)
+            .key(&image_name)
+            .send()
+    })
+    .await?;
+
+    let image_data = get_object_output
+        .body
+        .collect()
         .await
-        .unwrap();
-
-    let image_data = get_object_output.body.collect().await.unwrap().into_bytes();
-    let mut img = image::load_from_memory(&image_data).unwrap().to_rgb8();
+        .map_err(|e| AppError::Internal(format!("failed to read S3 object body: {e}")))?
+        .into_bytes();
+    let mut img = image::load_from_memory(&image_data)
+        .map_err(|e| AppError::Internal(format!("failed to decode image: {e}")))?
+        .to_rgb8();
 
     // Analyze the document with Textract
     let document = Document::builder()
         .s3_object(
-            aws_sdk_textract::types::S3Object::builder()
+            S3Object::builder()
                 .bucket(bucket)
                 .name(&image_name)
                 .build(),
         )
         .build();
 
-    let resp = state
-        .textract_client
-        .analyze_document()
-        .feature_types("FORMS".into())
-        .document(document)
-        .send()
-        .await
-        .unwrap();
+    let resp = retry(|| {
+        state
+            .textract_client
+            .analyze_document()
+            .feature_types("FORMS".into())
+            .feature_types("TABLES".into())
+            .document(document.clone())
+            .send()
+    })
+    .await?;
 
     let blocks = resp.blocks();
     let key_value_pairs = extract_key_value_pairs(blocks);
+    let cell_boxes = tables::cell_bounding_boxes(blocks);
 
     // Draw bounding boxes
     let font = Vec::from(include_bytes!("roboto.ttf") as &[u8]);
@@ -127,16 +218,18 @@ async fn display_image(
         }
     }
 
-    // Convert image to bytes
-    let mut buffer = Cursor::new(Vec::new());
-    img.write_to(&mut buffer, image::ImageOutputFormat::Png)
-        .unwrap();
+    for cell_box in &cell_boxes {
+        draw_bounding_box(&mut img, cell_box, Rgb([0, 200, 0]), 1); // Green for table cells
+    }
+
+    // Convert image to bytes in the negotiated format
+    let encoded = encode_image(&img, output_format, quality, lossless);
 
     // Return the image
-    (
-        [(axum::http::header::CONTENT_TYPE, "image/png")],
-        buffer.into_inner(),
-    )
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, output_format.content_type())],
+        encoded,
+    ))
 }
 
 fn draw_bounding_box(
@@ -181,29 +274,39 @@ fn draw_text(
 async fn analyze_image(
     Path(image_name): Path<String>,
     State(state): State<Arc<AppState>>,
-) -> Json<Vec<KeyValuePair>> {
-    // Specify the S3 bucket and document
-    let bucket = "smartflow-dev";
+) -> Result<Json<Vec<KeyValuePair>>, AppError> {
+    let key_value_pairs = analyze_document_key(&state, &image_name).await?;
+    Ok(Json(key_value_pairs))
+}
+
+// Run synchronous `AnalyzeDocument` (FORMS) against an S3 object and return its
+// key-value pairs, sorted. Shared by the single-document and batch routes.
+pub(crate) async fn analyze_document_key(
+    state: &AppState,
+    key: &str,
+) -> Result<Vec<KeyValuePair>, AppError> {
+    let bucket = state.config.bucket.as_str();
 
     // Create the Document object
     let document = Document::builder()
         .s3_object(
-            aws_sdk_textract::types::S3Object::builder()
+            S3Object::builder()
                 .bucket(bucket)
-                .name(&image_name)
+                .name(key)
                 .build(),
         )
         .build();
 
     // Call Textract to analyze the document
-    let resp = state
-        .textract_client
-        .analyze_document()
-        .feature_types("FORMS".into())
-        .document(document)
-        .send()
-        .await
-        .unwrap();
+    let resp = retry(|| {
+        state
+            .textract_client
+            .analyze_document()
+            .feature_types("FORMS".into())
+            .document(document.clone())
+            .send()
+    })
+    .await?;
 
     // Process the results
     let blocks = resp.blocks();
@@ -212,18 +315,130 @@ async fn analyze_image(
     // Sort the key_value_pairs
     sort_key_value_pairs(&mut key_value_pairs);
 
-    Json(key_value_pairs)
+    Ok(key_value_pairs)
+}
+
+// Analyze a (potentially multi-page) PDF that lives in S3, using the asynchronous
+// `StartDocumentAnalysis`/`GetDocumentAnalysis` API instead of the synchronous
+// `AnalyzeDocument` call, which only supports single images/single-page documents.
+async fn analyze_document_async(
+    Path(doc_name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<KeyValuePair>>, AppError> {
+    let bucket = state.config.bucket.as_str();
+
+    let document_location = DocumentLocation::builder()
+        .s3_object(S3Object::builder().bucket(bucket).name(&doc_name).build())
+        .build();
+
+    let start_resp = retry(|| {
+        state
+            .textract_client
+            .start_document_analysis()
+            .feature_types("FORMS".into())
+            .document_location(document_location.clone())
+            .send()
+    })
+    .await?;
+
+    let job_id = start_resp
+        .job_id()
+        .ok_or_else(|| AppError::Internal("StartDocumentAnalysis response had no JobId".into()))?
+        .to_string();
+    let blocks = poll_document_analysis(&state.textract_client, &job_id).await?;
+
+    let mut key_value_pairs = extract_key_value_pairs(&blocks);
+    sort_key_value_pairs(&mut key_value_pairs);
+
+    Ok(Json(key_value_pairs))
+}
+
+// Poll `GetDocumentAnalysis` until the job reaches a terminal state, accumulating every
+// `Block` across all paginated result pages (following `NextToken`) into a single `Vec`.
+async fn poll_document_analysis(
+    client: &TextractClient,
+    job_id: &str,
+) -> Result<Vec<Block>, AppError> {
+    let mut attempts = 0;
+    loop {
+        let resp = retry(|| client.get_document_analysis().job_id(job_id).send()).await?;
+
+        match resp.job_status() {
+            Some(JobStatus::Succeeded) | Some(JobStatus::PartialSuccess) => {
+                return collect_all_blocks(client, job_id, resp).await;
+            }
+            Some(JobStatus::Failed) => {
+                return Err(AppError::Internal(format!(
+                    "Textract job {job_id} failed: {:?}",
+                    resp.status_message()
+                )));
+            }
+            _ => {
+                attempts += 1;
+                if attempts >= MAX_POLL_ATTEMPTS {
+                    return Err(AppError::Internal(format!(
+                        "Textract job {job_id} did not complete in time"
+                    )));
+                }
+                tokio::time::sleep(backoff_delay_with(POLL_BASE_DELAY, POLL_MAX_DELAY, attempts)).await;
+            }
+        }
+    }
+}
+
+// Walk every page of a completed `GetDocumentAnalysis` result via `NextToken`,
+// accumulating all blocks into one `Vec` in page order.
+async fn collect_all_blocks(
+    client: &TextractClient,
+    job_id: &str,
+    first_page: aws_sdk_textract::operation::get_document_analysis::GetDocumentAnalysisOutput,
+) -> Result<Vec<Block>, AppError> {
+    let mut blocks = first_page.blocks().to_vec();
+    let mut next_token = first_page.next_token().map(|t| t.to_string());
+
+    while let Some(token) = next_token {
+        let resp = retry(|| {
+            client
+                .get_document_analysis()
+                .job_id(job_id)
+                .next_token(&token)
+                .send()
+        })
+        .await?;
+
+        blocks.extend(resp.blocks().to_vec());
+        next_token = resp.next_token().map(|t| t.to_string());
+    }
+
+    Ok(blocks)
+}
+
+// Build an id -> block lookup map, the starting point for every relationship walk over
+// a Textract block list (key-value pairs, tables, queries).
+pub(crate) fn build_block_map(blocks: &[Block]) -> HashMap<String, &Block> {
+    blocks
+        .iter()
+        .filter_map(|block| block.id().map(|id| (id.to_string(), block)))
+        .collect()
+}
+
+// Convert a Textract Geometry's bounding box into our serializable BoundingBox.
+pub(crate) fn to_bounding_box(geometry: Option<&aws_sdk_textract::types::Geometry>) -> Option<BoundingBox> {
+    geometry.and_then(|g| g.bounding_box()).map(|bb| BoundingBox {
+        width: bb.width(),
+        height: bb.height(),
+        left: bb.left(),
+        top: bb.top(),
+    })
 }
 
 fn extract_key_value_pairs(blocks: &[Block]) -> Vec<KeyValuePair> {
+    let block_map = build_block_map(blocks);
     let mut key_map = HashMap::new();
     let mut value_map = HashMap::new();
-    let mut block_map = HashMap::new();
 
     for block in blocks {
         if let Some(block_id) = block.id() {
-            block_map.insert(block_id.to_string(), block);
-
             if block.block_type() == Some(&BlockType::KeyValueSet) {
                 if block.entity_types().contains(&EntityType::Key) {
                     key_map.insert(block_id.to_string(), block);
@@ -238,15 +453,7 @@ fn extract_key_value_pairs(blocks: &[Block]) -> Vec<KeyValuePair> {
 
     for (_, key_block) in key_map {
         let key_text = get_text_for_block(key_block, &block_map);
-        let key_bounding_box = key_block
-            .geometry()
-            .and_then(|g| g.bounding_box())
-            .map(|bb| BoundingBox {
-                width: bb.width(),
-                height: bb.height(),
-                left: bb.left(),
-                top: bb.top(),
-            });
+        let key_bounding_box = to_bounding_box(key_block.geometry());
 
         let relationships = key_block.relationships();
         for relationship in relationships {
@@ -254,21 +461,14 @@ fn extract_key_value_pairs(blocks: &[Block]) -> Vec<KeyValuePair> {
                 for value_block_id in relationship.ids() {
                     if let Some(value_block) = value_map.get(value_block_id) {
                         let value_text = get_text_for_block(value_block, &block_map);
-                        let value_bounding_box = value_block
-                            .geometry()
-                            .and_then(|g| g.bounding_box())
-                            .map(|bb| BoundingBox {
-                                width: bb.width(),
-                                height: bb.height(),
-                                left: bb.left(),
-                                top: bb.top(),
-                            });
+                        let value_bounding_box = to_bounding_box(value_block.geometry());
 
                         key_value_pairs.push(KeyValuePair {
                             key: key_text.clone(),
                             value: value_text,
                             key_bounding_box: key_bounding_box.clone(),
                             value_bounding_box,
+                            page: key_block.page().unwrap_or(1),
                         });
                     }
                 }
@@ -279,7 +479,7 @@ fn extract_key_value_pairs(blocks: &[Block]) -> Vec<KeyValuePair> {
     key_value_pairs
 }
 
-fn get_text_for_block(block: &Block, block_map: &HashMap<String, &Block>) -> String {
+pub(crate) fn get_text_for_block(block: &Block, block_map: &HashMap<String, &Block>) -> String {
     let mut text = String::new();
 
     let relationships = block.relationships();
@@ -312,7 +512,7 @@ fn sort_key_value_pairs(key_value_pairs: &mut Vec<KeyValuePair>) {
             .as_ref()
             .or(b.value_bounding_box.as_ref());
 
-        match (a_box, b_box) {
+        a.page.cmp(&b.page).then_with(|| match (a_box, b_box) {
             (Some(a), Some(b)) => a
                 .top
                 .partial_cmp(&b.top)
@@ -325,6 +525,6 @@ fn sort_key_value_pairs(key_value_pairs: &mut Vec<KeyValuePair>) {
             (Some(_), None) => std::cmp::Ordering::Less,
             (None, Some(_)) => std::cmp::Ordering::Greater,
             (None, None) => std::cmp::Ordering::Equal,
-        }
+        })
     });
 }
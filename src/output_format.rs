@@ -0,0 +1,113 @@
+use image::{ImageBuffer, Rgb};
+use std::io::Cursor;
+
+// Image format the annotated document can be re-encoded as. PNG is lossless but large;
+// WebP and JPEG trade some quality for a much smaller payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    WebP,
+}
+
+impl OutputFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "image/png",
+            OutputFormat::Jpeg => "image/jpeg",
+            OutputFormat::WebP => "image/webp",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Some(OutputFormat::Png),
+            "jpeg" | "jpg" => Some(OutputFormat::Jpeg),
+            "webp" => Some(OutputFormat::WebP),
+            _ => None,
+        }
+    }
+
+    // Pick a format from the ?format= query param first, falling back to the Accept
+    // header's listed media types, defaulting to PNG if neither names a supported format.
+    pub fn negotiate(query_format: Option<&str>, accept_header: Option<&str>) -> Self {
+        query_format
+            .and_then(Self::from_name)
+            .or_else(|| {
+                accept_header.and_then(|accept| {
+                    accept.split(',').find_map(|part| {
+                        match part.split(';').next().unwrap_or("").trim() {
+                            "image/webp" => Some(OutputFormat::WebP),
+                            "image/jpeg" => Some(OutputFormat::Jpeg),
+                            "image/png" => Some(OutputFormat::Png),
+                            _ => None,
+                        }
+                    })
+                })
+            })
+            .unwrap_or(OutputFormat::Png)
+    }
+}
+
+// Re-encode an annotated RGB image in the negotiated format. quality (0-100) only
+// applies to JPEG/WebP and is ignored for PNG. lossless only applies to WebP, selecting
+// `WebPEncoder`'s lossless mode instead of its quality-tunable lossy mode.
+pub fn encode_image(
+    img: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    format: OutputFormat,
+    quality: u8,
+    lossless: bool,
+) -> Vec<u8> {
+    match format {
+        OutputFormat::Png => {
+            let mut buffer = Cursor::new(Vec::new());
+            img.write_to(&mut buffer, image::ImageOutputFormat::Png)
+                .unwrap();
+            buffer.into_inner()
+        }
+        OutputFormat::Jpeg => {
+            let mut buffer = Cursor::new(Vec::new());
+            img.write_to(&mut buffer, image::ImageOutputFormat::Jpeg(quality.min(100)))
+                .unwrap();
+            buffer.into_inner()
+        }
+        OutputFormat::WebP => {
+            let encoder = webp::Encoder::from_rgb(img.as_raw(), img.width(), img.height());
+            if lossless {
+                encoder.encode_lossless().to_vec()
+            } else {
+                encoder.encode(quality.min(100) as f32).to_vec()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_param_wins_over_accept_header() {
+        assert_eq!(
+            OutputFormat::negotiate(Some("webp"), Some("image/png")),
+            OutputFormat::WebP
+        );
+    }
+
+    #[test]
+    fn falls_back_to_accept_header_when_no_query_param() {
+        assert_eq!(
+            OutputFormat::negotiate(None, Some("text/html, image/jpeg;q=0.9")),
+            OutputFormat::Jpeg
+        );
+    }
+
+    #[test]
+    fn defaults_to_png_when_nothing_matches() {
+        assert_eq!(OutputFormat::negotiate(None, None), OutputFormat::Png);
+        assert_eq!(
+            OutputFormat::negotiate(Some("bmp"), Some("text/html")),
+            OutputFormat::Png
+        );
+    }
+}
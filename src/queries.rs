@@ -0,0 +1,174 @@
+use aws_sdk_textract::types::{Block, BlockType, Document, QueriesConfig, Query, RelationshipType};
+use axum::extract::{Path, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::AppError;
+use crate::retry::retry;
+use crate::{build_block_map, to_bounding_box, AppState, BoundingBox};
+
+#[derive(Debug, Deserialize)]
+pub struct QueryRequest {
+    pub questions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueryAnswer {
+    pub question: String,
+    pub answer: Option<String>,
+    pub confidence: Option<f32>,
+    pub bounding_box: Option<BoundingBox>,
+}
+
+// POST /query/:image_name — analyze a document with the QUERIES feature, asking each of
+// questions as a natural-language query, and return the best answer to each one.
+pub async fn query_image(
+    Path(image_name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<QueryRequest>,
+) -> Result<Json<Vec<QueryAnswer>>, AppError> {
+    let bucket = state.config.bucket.as_str();
+
+    let document = Document::builder()
+        .s3_object(
+            aws_sdk_textract::types::S3Object::builder()
+                .bucket(bucket)
+                .name(&image_name)
+                .build(),
+        )
+        .build();
+
+    let queries_config = QueriesConfig::builder()
+        .set_queries(Some(
+            request
+                .questions
+                .iter()
+                .map(|question| Query::builder().text(question).build().unwrap())
+                .collect(),
+        ))
+        .build()
+        .unwrap();
+
+    let resp = retry(|| {
+        state
+            .textract_client
+            .analyze_document()
+            .feature_types("QUERIES".into())
+            .document(document.clone())
+            .queries_config(queries_config.clone())
+            .send()
+    })
+    .await?;
+
+    Ok(Json(extract_query_answers(resp.blocks())))
+}
+
+// Match each BlockType::Query block to its best BlockType::QueryResult child (via
+// RelationshipType::Answer), picked by highest confidence.
+pub fn extract_query_answers(blocks: &[Block]) -> Vec<QueryAnswer> {
+    let block_map = build_block_map(blocks);
+
+    blocks
+        .iter()
+        .filter(|block| block.block_type() == Some(&BlockType::Query))
+        .map(|query_block| build_answer(query_block, &block_map))
+        .collect()
+}
+
+fn build_answer(query_block: &Block, block_map: &HashMap<String, &Block>) -> QueryAnswer {
+    let question = query_block
+        .query()
+        .map(|q| q.text())
+        .unwrap_or_default()
+        .to_string();
+
+    let best_result = query_block
+        .relationships()
+        .iter()
+        .filter(|relationship| relationship.r#type() == Some(&RelationshipType::Answer))
+        .flat_map(|relationship| relationship.ids())
+        .filter_map(|id| block_map.get(id))
+        .filter(|block| block.block_type() == Some(&BlockType::QueryResult))
+        .max_by(|a, b| {
+            a.confidence()
+                .unwrap_or(0.0)
+                .partial_cmp(&b.confidence().unwrap_or(0.0))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+    match best_result {
+        Some(result) => QueryAnswer {
+            question,
+            answer: result.text().map(|t| t.to_string()),
+            confidence: result.confidence(),
+            bounding_box: to_bounding_box(result.geometry()),
+        },
+        None => QueryAnswer {
+            question,
+            answer: None,
+            confidence: None,
+            bounding_box: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_textract::types::Relationship;
+
+    fn query_block(id: &str, question: &str, result_ids: &[&str]) -> Block {
+        let mut relationship = Relationship::builder().r#type(RelationshipType::Answer);
+        for result_id in result_ids {
+            relationship = relationship.ids(*result_id);
+        }
+        Block::builder()
+            .id(id)
+            .block_type(BlockType::Query)
+            .query(Query::builder().text(question).build().unwrap())
+            .relationships(relationship.build())
+            .build()
+    }
+
+    fn query_result(id: &str, text: &str, confidence: f32) -> Block {
+        Block::builder()
+            .id(id)
+            .block_type(BlockType::QueryResult)
+            .text(text)
+            .confidence(confidence)
+            .build()
+    }
+
+    #[test]
+    fn picks_the_highest_confidence_answer() {
+        let blocks = vec![
+            query_block("query", "What is the total?", &["low", "high"]),
+            query_result("low", "$10", 40.0),
+            query_result("high", "$100", 95.0),
+        ];
+
+        let block_map = build_block_map(&blocks);
+        let query_block = blocks.iter().find(|b| b.id() == Some("query")).unwrap();
+        let answer = build_answer(query_block, &block_map);
+
+        assert_eq!(answer.question, "What is the total?");
+        assert_eq!(answer.answer.as_deref(), Some("$100"));
+        assert_eq!(answer.confidence, Some(95.0));
+    }
+
+    #[test]
+    fn no_query_result_yields_none_answer() {
+        let blocks = vec![query_block("query", "What is the due date?", &[])];
+
+        let block_map = build_block_map(&blocks);
+        let query_block = &blocks[0];
+        let answer = build_answer(query_block, &block_map);
+
+        assert_eq!(answer.question, "What is the due date?");
+        assert_eq!(answer.answer, None);
+        assert_eq!(answer.confidence, None);
+        assert!(answer.bounding_box.is_none());
+    }
+}
@@ -0,0 +1,83 @@
+use aws_smithy_runtime_api::client::result::SdkError;
+use aws_smithy_types::error::metadata::ProvideErrorMetadata;
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::error::is_retryable_code;
+
+const BASE_DELAY: Duration = Duration::from_millis(100);
+const MAX_DELAY: Duration = Duration::from_secs(20);
+const MAX_ATTEMPTS: u32 = 5;
+
+// min(cap, base * 2^attempt), scaled by a random jitter factor in [0.5, 1.0] to avoid
+// a thundering herd of concurrent retries.
+pub(crate) fn backoff_delay_with(base: Duration, cap: Duration, attempt: u32) -> Duration {
+    let exp = base
+        .checked_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+        .unwrap_or(cap);
+    let capped = exp.min(cap);
+    let jitter: f64 = rand::thread_rng().gen_range(0.5..=1.0);
+    capped.mul_f64(jitter)
+}
+
+fn backoff_delay(attempt: u32) -> Duration {
+    backoff_delay_with(BASE_DELAY, MAX_DELAY, attempt)
+}
+
+fn is_retryable<E, R>(err: &SdkError<E, R>) -> bool
+where
+    E: ProvideErrorMetadata,
+{
+    match err.code() {
+        Some(code) => is_retryable_code(code),
+        None => matches!(
+            err,
+            SdkError::TimeoutError(_) | SdkError::DispatchFailure(_)
+        ),
+    }
+}
+
+// Run op, retrying with exponential backoff and jitter on throttling/transient errors,
+// up to MAX_ATTEMPTS attempts total.
+pub async fn retry<T, E, R, F, Fut>(mut op: F) -> Result<T, SdkError<E, R>>
+where
+    E: ProvideErrorMetadata,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SdkError<E, R>>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt + 1 < MAX_ATTEMPTS && is_retryable(&err) => {
+                tokio::time::sleep(backoff_delay(attempt)).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially_within_jitter_bounds() {
+        for attempt in 0..4 {
+            let base = BASE_DELAY.mul_f64(2f64.powi(attempt as i32));
+            let delay = backoff_delay(attempt);
+            assert!(delay >= base.mul_f64(0.5));
+            assert!(delay <= base);
+        }
+    }
+
+    #[test]
+    fn backoff_is_capped_at_max_delay() {
+        for attempt in [10, 20, u32::MAX] {
+            let delay = backoff_delay(attempt);
+            assert!(delay <= MAX_DELAY);
+        }
+    }
+}
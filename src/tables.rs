@@ -0,0 +1,206 @@
+use aws_sdk_textract::types::{Block, BlockType, Document, RelationshipType};
+use axum::extract::{Path, State};
+use axum::Json;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::AppError;
+use crate::retry::retry;
+use crate::{build_block_map, get_text_for_block, to_bounding_box, AppState, BoundingBox};
+
+// A reconstructed TABLES result: the table's own bounding box plus a dense row/column
+// grid of cell text (spanned cells are repeated, missing cells are empty).
+#[derive(Debug, Serialize)]
+pub struct Table {
+    pub bounding_box: Option<BoundingBox>,
+    pub rows: Vec<Vec<String>>,
+}
+
+// GET /tables/:image_name — analyze a document with the TABLES feature and return every
+// table reconstructed into rows/columns.
+pub async fn tables_for_image(
+    Path(image_name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Table>>, AppError> {
+    let bucket = state.config.bucket.as_str();
+
+    let document = Document::builder()
+        .s3_object(
+            aws_sdk_textract::types::S3Object::builder()
+                .bucket(bucket)
+                .name(&image_name)
+                .build(),
+        )
+        .build();
+
+    let resp = retry(|| {
+        state
+            .textract_client
+            .analyze_document()
+            .feature_types("TABLES".into())
+            .document(document.clone())
+            .send()
+    })
+    .await?;
+
+    Ok(Json(extract_tables(resp.blocks())))
+}
+
+// Walk every BlockType::Table block, follow its Child relationships to BlockType::Cell
+// blocks, and reconstruct a dense 2D grid from each cell's row/column index and span.
+pub fn extract_tables(blocks: &[Block]) -> Vec<Table> {
+    let block_map = build_block_map(blocks);
+
+    blocks
+        .iter()
+        .filter(|block| block.block_type() == Some(&BlockType::Table))
+        .map(|table_block| build_table(table_block, &block_map))
+        .collect()
+}
+
+// Every table cell's bounding box, regardless of which table it belongs to — used to
+// draw cell borders over an annotated image without needing the full grid structure.
+pub fn cell_bounding_boxes(blocks: &[Block]) -> Vec<BoundingBox> {
+    blocks
+        .iter()
+        .filter(|block| block.block_type() == Some(&BlockType::Cell))
+        .filter_map(|block| to_bounding_box(block.geometry()))
+        .collect()
+}
+
+fn build_table(table_block: &Block, block_map: &HashMap<String, &Block>) -> Table {
+    let bounding_box = to_bounding_box(table_block.geometry());
+
+    let mut cells = Vec::new();
+    let mut row_count = 0usize;
+    let mut column_count = 0usize;
+
+    for relationship in table_block.relationships() {
+        if relationship.r#type() != Some(&RelationshipType::Child) {
+            continue;
+        }
+        for cell_id in relationship.ids() {
+            let Some(cell_block) = block_map.get(cell_id) else {
+                continue;
+            };
+            if cell_block.block_type() != Some(&BlockType::Cell) {
+                continue;
+            }
+
+            let row_index = cell_block.row_index().unwrap_or(1).max(1) as usize;
+            let column_index = cell_block.column_index().unwrap_or(1).max(1) as usize;
+            let row_span = cell_block.row_span().unwrap_or(1).max(1) as usize;
+            let column_span = cell_block.column_span().unwrap_or(1).max(1) as usize;
+            let text = get_text_for_block(cell_block, block_map);
+
+            row_count = row_count.max(row_index + row_span - 1);
+            column_count = column_count.max(column_index + column_span - 1);
+            cells.push((row_index, column_index, row_span, column_span, text));
+        }
+    }
+
+    let mut rows = vec![vec![String::new(); column_count]; row_count];
+    for (row_index, column_index, row_span, column_span, text) in cells {
+        for r in 0..row_span {
+            for c in 0..column_span {
+                if let Some(row) = rows.get_mut(row_index - 1 + r) {
+                    if let Some(cell) = row.get_mut(column_index - 1 + c) {
+                        *cell = text.clone();
+                    }
+                }
+            }
+        }
+    }
+
+    Table { bounding_box, rows }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_textract::types::Relationship;
+
+    fn word(id: &str, text: &str) -> Block {
+        Block::builder()
+            .id(id)
+            .block_type(BlockType::Word)
+            .text(text)
+            .build()
+    }
+
+    fn cell(
+        id: &str,
+        row_index: i32,
+        column_index: i32,
+        row_span: i32,
+        column_span: i32,
+        word_id: &str,
+    ) -> Block {
+        Block::builder()
+            .id(id)
+            .block_type(BlockType::Cell)
+            .row_index(row_index)
+            .column_index(column_index)
+            .row_span(row_span)
+            .column_span(column_span)
+            .relationships(
+                Relationship::builder()
+                    .r#type(RelationshipType::Child)
+                    .ids(word_id)
+                    .build(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn fills_spanned_cells_and_leaves_missing_cells_empty() {
+        let blocks = vec![
+            Block::builder()
+                .id("table")
+                .block_type(BlockType::Table)
+                .relationships(
+                    Relationship::builder()
+                        .r#type(RelationshipType::Child)
+                        .ids("header")
+                        .ids("a")
+                        .ids("b")
+                        .ids("c")
+                        .build(),
+                )
+                .build(),
+            cell("header", 1, 1, 1, 2, "header-word"),
+            cell("a", 2, 1, 1, 1, "a-word"),
+            cell("b", 2, 2, 1, 1, "b-word"),
+            // Row 3 has no cell in column 1.
+            cell("c", 3, 2, 1, 1, "c-word"),
+            word("header-word", "Header"),
+            word("a-word", "A"),
+            word("b-word", "B"),
+            word("c-word", "C"),
+        ];
+
+        let block_map = build_block_map(&blocks);
+        let table_block = blocks.iter().find(|b| b.id() == Some("table")).unwrap();
+        let table = build_table(table_block, &block_map);
+
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["Header".to_string(), "Header".to_string()],
+                vec!["A".to_string(), "B".to_string()],
+                vec![String::new(), "C".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_table_has_no_rows() {
+        let blocks = vec![Block::builder().id("table").block_type(BlockType::Table).build()];
+        let block_map = build_block_map(&blocks);
+        let table_block = &blocks[0];
+        let table = build_table(table_block, &block_map);
+
+        assert!(table.rows.is_empty());
+    }
+}